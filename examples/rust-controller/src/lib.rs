@@ -1,3 +1,10 @@
+// `bindings` is generated from this component's WIT world at build time.
+// The WIT source isn't checked into this repo, so it can't be extended here
+// to mirror the continuous-motion `location`/`velocity`/`acceleration`
+// fields and motor command (`set_motor_acceleration` in public/rust/game.rs,
+// `setmotor` in examples/rust-elevator-wasm) added elsewhere. This component
+// still only issues discrete `go_to_floor` commands until that WIT world
+// is updated out-of-tree and regenerated.
 #[allow(warnings)]
 mod bindings;
 
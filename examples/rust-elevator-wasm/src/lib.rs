@@ -3,6 +3,8 @@
 extern "C" {
     // Import function that the JS runtime will provide
     fn gofloor(elevator_id: u32, floor_num: u32);
+    // Drive a car by target acceleration instead of teleporting to a floor
+    fn setmotor(elevator_id: u32, acceleration: f32);
 }
 
 // Data structures matching the JavaScript memory layout
@@ -14,6 +16,9 @@ pub struct Elevator {
     pub pressed_floors_ptr: *const u32,
     pub pressed_floors_len: u32,
     pub load: f32,
+    pub location: f32,     // height above the bottom of the shaft, in meters
+    pub velocity: f32,     // signed, meters/tick
+    pub acceleration: f32, // signed, meters/tick^2
 }
 
 #[repr(C)]
@@ -23,6 +28,8 @@ pub struct Floor {
     pub down_button: u32, // bool as u32 (0 or 1)
 }
 
+const MOTOR_A_MAX: f32 = 1.0;
+
 #[derive(Clone, Copy, PartialEq)]
 enum Direction {
     Up,
@@ -50,34 +57,79 @@ pub extern "C" fn tick(
     elevators_len: u32,
     floors_ptr: u32,
     floors_len: u32,
+    floor_heights_ptr: u32,
+    floor_heights_len: u32,
 ) {
     let controller = get_controller();
-    
+
     // Parse elevator data from memory
     let elevators = unsafe {
         core::slice::from_raw_parts(elevators_ptr as *const Elevator, elevators_len as usize)
     };
-    
+
     // Parse floor data from memory
     let floors = unsafe {
         core::slice::from_raw_parts(floors_ptr as *const Floor, floors_len as usize)
     };
-    
+
+    // Parse the building's cumulative floor heights, used to drive `setmotor`
+    let floor_heights = unsafe {
+        core::slice::from_raw_parts(floor_heights_ptr as *const f32, floor_heights_len as usize)
+    };
+
     // Run the intelligent elevator algorithm
     for (elevator_id, elevator) in elevators.iter().enumerate() {
         let target_floor = controller.get_best_target_floor(elevator, floors);
-        
+
         // Only send command if we have a different target
         if target_floor != elevator.current_floor {
             unsafe {
                 gofloor(elevator_id as u32, target_floor);
             }
         }
+
+        // Drive the car physically toward its target floor's height
+        if let Some(&target_height) = floor_heights.get(target_floor as usize) {
+            let acceleration = motor_acceleration(elevator.location, elevator.velocity, target_height);
+            unsafe {
+                setmotor(elevator_id as u32, acceleration);
+            }
+        }
     }
-    
+
     controller.tick_count += 1;
 }
 
+/// Trapezoidal-profile acceleration command: brake if within stopping
+/// distance of `target_height`, otherwise accelerate toward it.
+///
+/// Assumes the host steps physics one tick at a time the way `game.rs`'s
+/// `SmoothMotionController` does, so it can look a tick ahead and check
+/// whether the naive braking law above would overshoot the target — if so
+/// it commands a hard brake instead, the same fix applied to
+/// `SmoothMotionController::tick` for the same oscillation.
+fn motor_acceleration(location: f32, velocity: f32, target_height: f32) -> f32 {
+    const DT: f32 = 1.0;
+
+    let d = target_height - location;
+    let d_brake = (velocity * velocity) / (2.0 * MOTOR_A_MAX);
+
+    let accel = if d.abs() <= d_brake {
+        -velocity.signum() * MOTOR_A_MAX
+    } else {
+        d.signum() * MOTOR_A_MAX
+    };
+
+    let new_location = location + (velocity + accel * DT) * DT;
+    let new_d = target_height - new_location;
+
+    if new_d.signum() != d.signum() || new_d.abs() <= 0.01 {
+        -velocity.signum() * MOTOR_A_MAX
+    } else {
+        accel
+    }
+}
+
 /// Intelligent elevator controller using real game data
 pub struct SimpleElevatorController {
     /// Tick counter for timing
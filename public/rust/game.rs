@@ -1,12 +1,43 @@
-use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
 
+/// Small deterministic PRNG (SplitMix64) so the simulator/tournament/
+/// trainer don't need an external crate dependency — this file has no
+/// accompanying `Cargo.toml` and ships as a freestanding source file.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[derive(Clone)]
 pub struct Elevator {
     id: u32,
     current_floor_val: i32,
     destination_floor_val: Option<i32>,
     percent_full_val: f32,
+    location_val: f32,
+    velocity_val: f32,
+    acceleration_val: f32,
     pressed_buttons: Vec<i32>,
-    commands: Vec<(u32, i32)>,
+    commands: Vec<(u32, Command)>,
 }
 
 impl Elevator {
@@ -22,17 +53,43 @@ impl Elevator {
         self.percent_full_val
     }
 
+    /// Height above the bottom of the shaft, in meters.
+    pub fn location(&self) -> f32 {
+        self.location_val
+    }
+
+    /// Signed velocity in meters/tick; positive is upward.
+    pub fn velocity(&self) -> f32 {
+        self.velocity_val
+    }
+
+    /// Signed acceleration in meters/tick^2; positive is upward.
+    pub fn acceleration(&self) -> f32 {
+        self.acceleration_val
+    }
+
     pub fn pressed_floor_buttons(&self) -> &[i32] {
         &self.pressed_buttons
     }
 
     pub fn go_to_floor(&mut self, floor: i32) {
-        self.commands.push((self.id, floor));
+        self.commands.push((self.id, Command::GoToFloor(floor)));
+    }
+
+    /// Drive the car with a target acceleration (meters/tick^2) instead of
+    /// teleporting to a floor. Lets a motion controller do its own
+    /// integration instead of relying on the built-in `go_to_floor` snap.
+    pub fn set_motor_acceleration(&mut self, acceleration: f32) {
+        self.commands.push((self.id, Command::Motor(acceleration)));
     }
 }
 
+#[derive(Clone)]
 pub struct Floor {
     level_val: i32,
+    /// Cumulative height of this floor above the bottom of the shaft, in
+    /// meters.
+    height_val: f32,
     up: bool,
     down: bool,
 }
@@ -42,6 +99,10 @@ impl Floor {
         self.level_val
     }
 
+    pub fn height(&self) -> f32 {
+        self.height_val
+    }
+
     pub fn button_up(&self) -> bool {
         self.up
     }
@@ -51,6 +112,14 @@ impl Floor {
     }
 }
 
+/// A queued command for a single elevator, tagged so `write_commands` can
+/// encode either a discrete floor target or a continuous motor input.
+#[derive(Clone)]
+enum Command {
+    GoToFloor(i32),
+    Motor(f32),
+}
+
 fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator>, Vec<Floor>)> {
     let mut buf4 = [0u8; 4];
 
@@ -72,6 +141,15 @@ fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator
         reader.read_exact(&mut buf4)?;
         let percent_full = f32::from_le_bytes(buf4);
 
+        reader.read_exact(&mut buf4)?;
+        let location = f32::from_le_bytes(buf4);
+
+        reader.read_exact(&mut buf4)?;
+        let velocity = f32::from_le_bytes(buf4);
+
+        reader.read_exact(&mut buf4)?;
+        let acceleration = f32::from_le_bytes(buf4);
+
         reader.read_exact(&mut buf4)?;
         let button_count = u32::from_le_bytes(buf4) as usize;
 
@@ -86,6 +164,9 @@ fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator
             current_floor_val: current_floor,
             destination_floor_val: destination_floor,
             percent_full_val: percent_full,
+            location_val: location,
+            velocity_val: velocity,
+            acceleration_val: acceleration,
             pressed_buttons,
             commands: Vec::new(),
         });
@@ -96,6 +177,9 @@ fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator
         reader.read_exact(&mut buf4)?;
         let level = i32::from_le_bytes(buf4);
 
+        reader.read_exact(&mut buf4)?;
+        let height = f32::from_le_bytes(buf4);
+
         let mut buf1 = [0u8; 1];
         reader.read_exact(&mut buf1)?;
         let up = buf1[0] != 0;
@@ -105,6 +189,7 @@ fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator
 
         floors.push(Floor {
             level_val: level,
+            height_val: height,
             up,
             down,
         });
@@ -113,30 +198,440 @@ fn read_state(reader: &mut BufReader<io::StdinLock>) -> io::Result<(Vec<Elevator
     Ok((elevators, floors))
 }
 
-fn write_commands(writer: &mut BufWriter<io::StdoutLock>, commands: &[(u32, i32)]) -> io::Result<()> {
+fn write_commands(writer: &mut BufWriter<io::StdoutLock>, commands: &[(u32, Command)]) -> io::Result<()> {
     writer.write_all(&(commands.len() as u32).to_le_bytes())?;
-    for &(elevator_id, target_floor) in commands {
+    for (elevator_id, command) in commands {
         writer.write_all(&elevator_id.to_le_bytes())?;
-        writer.write_all(&target_floor.to_le_bytes())?;
+        match command {
+            Command::GoToFloor(target_floor) => {
+                writer.write_all(&[0u8])?;
+                writer.write_all(&target_floor.to_le_bytes())?;
+            }
+            Command::Motor(acceleration) => {
+                writer.write_all(&[1u8])?;
+                writer.write_all(&acceleration.to_le_bytes())?;
+            }
+        }
     }
     writer.flush()
 }
 
-pub fn run<F: FnMut(&mut [Elevator], &[Floor])>(mut tick: F) {
+/// Converts a target floor into motion each tick using a trapezoidal
+/// velocity profile, so a controller can call `go_to_floor` and get
+/// realistic acceleration/braking instead of an instant teleport.
+pub struct SmoothMotionController {
+    pub a_max: f32,
+    pub v_max: f32,
+    pub epsilon: f32,
+}
+
+impl SmoothMotionController {
+    pub fn new(a_max: f32, v_max: f32) -> Self {
+        Self {
+            a_max,
+            v_max,
+            epsilon: 0.01,
+        }
+    }
+
+    /// Advances every elevator's `location`/`velocity`/`acceleration` by one
+    /// tick of length `dt`, steering toward each car's `destination_floor`
+    /// via `floor_heights`.
+    pub fn tick(&self, elevators: &mut [Elevator], floor_heights: &[f32], dt: f32) {
+        for elevator in elevators.iter_mut() {
+            let Some(destination) = elevator.destination_floor_val else {
+                elevator.acceleration_val = 0.0;
+                continue;
+            };
+            let Some(&target_height) = floor_heights.get(destination as usize) else {
+                continue;
+            };
+
+            let d = target_height - elevator.location_val;
+            let v = elevator.velocity_val;
+            let d_brake = (v * v) / (2.0 * self.a_max);
+
+            if d.abs() <= self.epsilon && v.abs() <= self.epsilon {
+                elevator.location_val = target_height;
+                elevator.velocity_val = 0.0;
+                elevator.acceleration_val = 0.0;
+                continue;
+            }
+
+            let accel = if d.abs() <= d_brake {
+                -v.signum() * self.a_max
+            } else {
+                d.signum() * self.a_max
+            };
+
+            let new_velocity = (v + accel * dt).clamp(-self.v_max, self.v_max);
+            let new_location = elevator.location_val + new_velocity * dt;
+            let new_d = target_height - new_location;
+
+            // At coarse dt the law above can overshoot the target hard
+            // enough that the next tick's d/d_brake sends it accelerating
+            // back the other way, oscillating forever. Detect that crossing
+            // here and snap to a stop instead of taking the overshooting
+            // step.
+            if new_d.signum() != d.signum() || new_d.abs() <= self.epsilon {
+                elevator.location_val = target_height;
+                elevator.velocity_val = 0.0;
+                elevator.acceleration_val = 0.0;
+                continue;
+            }
+
+            elevator.acceleration_val = accel;
+            elevator.velocity_val = new_velocity;
+            elevator.location_val = new_location;
+
+            if let (Some(&min_height), Some(&max_height)) =
+                (floor_heights.first(), floor_heights.last())
+            {
+                let clamped = elevator.location_val.clamp(min_height, max_height);
+                if clamped != elevator.location_val {
+                    elevator.velocity_val = 0.0;
+                    elevator.acceleration_val = 0.0;
+                }
+                elevator.location_val = clamped;
+            }
+        }
+    }
+}
+
+/// A cheap-to-clone snapshot of the whole game: every elevator and every
+/// floor. Controllers that want to look ahead clone this, apply candidate
+/// commands through a [`Simulator`], and score the result.
+#[derive(Clone)]
+pub struct WorldState {
+    pub elevators: Vec<Elevator>,
+    pub floors: Vec<Floor>,
+}
+
+/// Deterministic step function for Monte-Carlo / rollout planning.
+/// Wraps the same motion model and passenger-spawn rules the live engine
+/// uses, so a controller can roll candidate command sets forward without
+/// touching stdin/stdout. `step` is not pure: each call advances the
+/// simulator's own tick, patience timers, give-up count and wait records,
+/// and `Simulator` is not `Clone`, so separate rollouts share that state.
+pub struct Simulator {
+    pub motion: SmoothMotionController,
+    pub dt: f32,
+    pub spawn_rate: f32,
+    /// How many ticks a floor call is allowed to wait before the waiting
+    /// passenger gives up.
+    pub patience_ticks: u64,
+    tick: u64,
+    scheduler: Scheduler<PatienceEvent>,
+    give_ups: u32,
+    /// Tick each currently-lit floor call started waiting, keyed by
+    /// `(floor_index, is_up)`, so a real wait duration can be measured
+    /// when the call is serviced instead of counting serviced ticks.
+    call_started: std::collections::HashMap<(usize, bool), u64>,
+    completed_waits: Vec<u32>,
+}
+
+struct PatienceEvent {
+    floor_index: usize,
+    direction: CallDirection,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CallDirection {
+    Up,
+    Down,
+}
+
+impl Simulator {
+    pub fn new(motion: SmoothMotionController, dt: f32, spawn_rate: f32, patience_ticks: u64) -> Self {
+        Self {
+            motion,
+            dt,
+            spawn_rate,
+            patience_ticks,
+            tick: 0,
+            scheduler: Scheduler::new(),
+            give_ups: 0,
+            call_started: std::collections::HashMap::new(),
+            completed_waits: Vec::new(),
+        }
+    }
+
+    /// Number of passengers whose patience expired before an elevator
+    /// serviced their floor call.
+    pub fn give_ups(&self) -> u32 {
+        self.give_ups
+    }
+
+    /// Drains the wait durations (in ticks) of every floor call serviced
+    /// since the last call to this method.
+    pub fn take_completed_waits(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.completed_waits)
+    }
+
+    /// Advances `state` by one tick, applying `commands` (elevator_id,
+    /// target_floor pairs, as returned by [`Policy::act`] or collected from
+    /// `go_to_floor` calls) before
+    /// integrating motion. Does not mutate `state`; returns the next one.
+    pub fn step(&mut self, state: &WorldState, commands: &[(u32, i32)]) -> WorldState {
+        let mut next = state.clone();
+
+        for &(elevator_id, target_floor) in commands {
+            if let Some(elevator) = next.elevators.iter_mut().find(|e| e.id == elevator_id) {
+                elevator.destination_floor_val = Some(target_floor);
+                if let Some(floor) = next.floors.get_mut(target_floor as usize) {
+                    let floor_index = target_floor as usize;
+                    if floor.up {
+                        self.record_wait_completed(floor_index, true);
+                    }
+                    if floor.down {
+                        self.record_wait_completed(floor_index, false);
+                    }
+                    floor.up = false;
+                    floor.down = false;
+                }
+            }
+        }
+
+        let floor_heights: Vec<f32> = next.floors.iter().map(|f| f.height_val).collect();
+        self.motion.tick(&mut next.elevators, &floor_heights, self.dt);
+
+        for elevator in next.elevators.iter_mut() {
+            elevator.current_floor_val = nearest_floor(&floor_heights, elevator.location_val);
+        }
+
+        self.tick += 1;
+        for event in self.scheduler.advance_to(self.tick) {
+            let still_waiting = next
+                .floors
+                .get(event.floor_index)
+                .is_some_and(|floor| match event.direction {
+                    CallDirection::Up => floor.up,
+                    CallDirection::Down => floor.down,
+                });
+            if still_waiting {
+                let floor = &mut next.floors[event.floor_index];
+                match event.direction {
+                    CallDirection::Up => floor.up = false,
+                    CallDirection::Down => floor.down = false,
+                }
+                self.call_started
+                    .remove(&(event.floor_index, event.direction == CallDirection::Up));
+                self.give_ups += 1;
+            }
+        }
+
+        next
+    }
+
+    /// Records that the floor call at `(floor_index, is_up)` was just
+    /// serviced, turning however long it waited into a completed wait
+    /// duration instead of counting the idle ticks an elevator sits there.
+    fn record_wait_completed(&mut self, floor_index: usize, is_up: bool) {
+        if let Some(started) = self.call_started.remove(&(floor_index, is_up)) {
+            self.completed_waits.push((self.tick - started) as u32);
+        }
+    }
+
+    /// Seeded passenger-call model: each floor's up/down buttons light up
+    /// independently with probability `spawn_rate` per tick, so a run is
+    /// fully reproducible from `rng`'s seed. Newly lit calls get a patience
+    /// timer so a call that never gets serviced is recorded as a give-up.
+    pub fn spawn(&mut self, floors: &mut [Floor], rng: &mut Rng) {
+        for (floor_index, floor) in floors.iter_mut().enumerate() {
+            if !floor.up && rng.next_f32() < self.spawn_rate {
+                floor.up = true;
+                self.call_started.insert((floor_index, true), self.tick);
+                self.scheduler.schedule(
+                    self.tick + self.patience_ticks,
+                    PatienceEvent {
+                        floor_index,
+                        direction: CallDirection::Up,
+                    },
+                );
+            }
+            if !floor.down && rng.next_f32() < self.spawn_rate {
+                floor.down = true;
+                self.call_started.insert((floor_index, false), self.tick);
+                self.scheduler.schedule(
+                    self.tick + self.patience_ticks,
+                    PatienceEvent {
+                        floor_index,
+                        direction: CallDirection::Down,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Index of the floor whose height is closest to `location`; used to
+/// derive an elevator's discrete `current_floor` from its continuous
+/// position after motion integration.
+fn nearest_floor(floor_heights: &[f32], location: f32) -> i32 {
+    floor_heights
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            (*a - location).abs().partial_cmp(&(*b - location).abs()).unwrap()
+        })
+        .map(|(index, _)| index as i32)
+        .unwrap_or(0)
+}
+
+const TIMING_WHEEL_BITS: u32 = 6;
+const TIMING_WHEEL_SLOTS: usize = 1 << TIMING_WHEEL_BITS;
+const TIMING_WHEEL_MASK: u64 = (TIMING_WHEEL_SLOTS as u64) - 1;
+const TIMING_WHEEL_LEVELS: usize = 4;
+
+struct TimingWheelLevel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    /// Bit `i` is set iff `slots[i]` is non-empty, so `take` can skip an
+    /// empty slot's `Vec` drain in O(1), and `Scheduler::next_stop` can find
+    /// the next occupied slot with a `trailing_zeros` scan instead of
+    /// probing every slot in between.
+    occupancy: u64,
+}
+
+impl<T> TimingWheelLevel<T> {
+    fn new() -> Self {
+        Self {
+            slots: (0..TIMING_WHEEL_SLOTS).map(|_| Vec::new()).collect(),
+            occupancy: 0,
+        }
+    }
+
+    fn push(&mut self, slot: usize, deadline_tick: u64, event: T) {
+        self.slots[slot].push((deadline_tick, event));
+        self.occupancy |= 1 << slot;
+    }
+
+    fn take(&mut self, slot: usize) -> Vec<(u64, T)> {
+        if self.occupancy & (1 << slot) == 0 {
+            return Vec::new();
+        }
+        self.occupancy &= !(1 << slot);
+        std::mem::take(&mut self.slots[slot])
+    }
+}
+
+/// A hierarchical timing wheel for scheduling time-based events (passenger
+/// patience timeouts, door open/close dwell, idle-repositioning delays)
+/// without scanning every pending timer each tick. Each level has 64 slots
+/// covering a wider span than the one below it; events cascade down a
+/// level as the clock reaches their span, so per-tick cost stays
+/// independent of how many timers are pending.
+struct Scheduler<T> {
+    levels: Vec<TimingWheelLevel<T>>,
+    now: u64,
+}
+
+impl<T> Scheduler<T> {
+    fn new() -> Self {
+        Self {
+            levels: (0..TIMING_WHEEL_LEVELS).map(|_| TimingWheelLevel::new()).collect(),
+            now: 0,
+        }
+    }
+
+    /// Schedules `event` to fire when the clock reaches `deadline_tick`,
+    /// which must be strictly greater than the current tick.
+    fn schedule(&mut self, deadline_tick: u64, event: T) {
+        let delta = deadline_tick.saturating_sub(self.now);
+        let mut level = 0;
+        let mut span = TIMING_WHEEL_SLOTS as u64;
+        while delta >= span && level + 1 < self.levels.len() {
+            level += 1;
+            span *= TIMING_WHEEL_SLOTS as u64;
+        }
+        let slot = ((deadline_tick >> (level as u32 * TIMING_WHEEL_BITS)) & TIMING_WHEEL_MASK) as usize;
+        self.levels[level].push(slot, deadline_tick, event);
+    }
+
+    /// The next tick worth stopping at, no later than `limit`: either the
+    /// next level-0 deadline (found via `trailing_zeros` on the occupancy
+    /// bitfield instead of probing every slot in between) or the next
+    /// cascade boundary, whichever comes first. A level-0 entry's deadline
+    /// is always less than `self.now + TIMING_WHEEL_SLOTS` (that's what
+    /// keeps it at level 0); one whose slot wraps below the current slot
+    /// lies past the next boundary and is found by the scan after that hop.
+    fn next_stop(&self, limit: u64) -> u64 {
+        let current_slot = (self.now & TIMING_WHEEL_MASK) as u32;
+        let next_boundary = (self.now / TIMING_WHEEL_SLOTS as u64 + 1) * TIMING_WHEEL_SLOTS as u64;
+
+        let next_occupied = if current_slot < TIMING_WHEEL_MASK as u32 {
+            match self.levels[0].occupancy >> (current_slot + 1) {
+                0 => next_boundary,
+                shifted => self.now + 1 + shifted.trailing_zeros() as u64,
+            }
+        } else {
+            next_boundary
+        };
+
+        next_occupied.min(limit)
+    }
+
+    /// Drains whatever is due at `at_tick`: its level-0 slot, plus a
+    /// cascade of every higher level whose boundary `at_tick` lands on.
+    fn fire(&mut self, at_tick: u64, expired: &mut Vec<T>) {
+        let slot0 = (at_tick & TIMING_WHEEL_MASK) as usize;
+        expired.extend(self.levels[0].take(slot0).into_iter().map(|(_, event)| event));
+
+        for level in 1..self.levels.len() {
+            if at_tick & ((1u64 << (level as u32 * TIMING_WHEEL_BITS)) - 1) != 0 {
+                break;
+            }
+            let slot = ((at_tick >> (level as u32 * TIMING_WHEEL_BITS)) & TIMING_WHEEL_MASK) as usize;
+            for (deadline, event) in self.levels[level].take(slot) {
+                if deadline <= at_tick {
+                    // Already due: `schedule` would re-home this into level
+                    // 0's slot for `at_tick`, but that slot was drained
+                    // earlier in this same call and won't be visited again
+                    // until it comes back around the wheel.
+                    expired.push(event);
+                } else {
+                    self.schedule(deadline, event);
+                }
+            }
+        }
+    }
+
+    /// Advances the clock to `tick`, returning every event whose deadline
+    /// has now passed. Each hop hands the clock straight to `next_stop`
+    /// instead of walking one tick at a time, so ticks with nothing
+    /// scheduled and no cascade due cost nothing to skip over.
+    fn advance_to(&mut self, tick: u64) -> Vec<T> {
+        let mut expired = Vec::new();
+        while self.now < tick {
+            self.now = self.next_stop(tick);
+            self.fire(self.now, &mut expired);
+        }
+        expired
+    }
+}
+
+/// Drives the stdin/stdout protocol loop shared by [`run`], [`run_with_patience`],
+/// and [`run_recording`]: read a tick's state, call `before_tick` (for
+/// bookkeeping that needs the floors before the controller sees them), run
+/// the controller, write its commands, then call `after_commands` (for
+/// bookkeeping or side effects that need the commands actually sent). Stops
+/// as soon as reading or writing fails, same as the bare loops it replaces.
+fn run_loop<F, B, A>(mut tick: F, mut before_tick: B, mut after_commands: A) -> io::Result<()>
+where
+    F: FnMut(&mut [Elevator], &[Floor]),
+    B: FnMut(&[Floor]),
+    A: FnMut(&[Elevator], &[Floor], &[(u32, Command)]) -> io::Result<()>,
+{
     let stdin = io::stdin();
     let stdout = io::stdout();
     let mut reader = BufReader::new(stdin.lock());
     let mut writer = BufWriter::new(stdout.lock());
 
-    loop {
-        let (mut elevators, floors) = match read_state(&mut reader) {
-            Ok(state) => state,
-            Err(_) => break,
-        };
+    while let Ok((mut elevators, floors)) = read_state(&mut reader) {
+        before_tick(&floors);
 
         tick(&mut elevators, &floors);
 
-        let commands: Vec<(u32, i32)> = elevators
+        let commands: Vec<(u32, Command)> = elevators
             .iter_mut()
             .flat_map(|e| e.commands.drain(..))
             .collect();
@@ -144,5 +639,869 @@ pub fn run<F: FnMut(&mut [Elevator], &[Floor])>(mut tick: F) {
         if write_commands(&mut writer, &commands).is_err() {
             break;
         }
+
+        after_commands(&elevators, &floors, &commands)?;
+    }
+
+    Ok(())
+}
+
+pub fn run<F: FnMut(&mut [Elevator], &[Floor])>(tick: F) {
+    let _ = run_loop(tick, |_| {}, |_, _, _| Ok(()));
+}
+
+/// Like [`run`], but also tracks how long each floor call has been waiting
+/// using the same patience [`Scheduler`] the offline [`Simulator`] rolls
+/// forward, so a live controller run can report give-ups instead of only
+/// whatever the engine's own state exposes. `on_give_up` is called once for
+/// every floor call whose patience timer fires while it's still unserviced.
+pub fn run_with_patience<F, G>(patience_ticks: u64, tick: F, mut on_give_up: G)
+where
+    F: FnMut(&mut [Elevator], &[Floor]),
+    G: FnMut(usize, bool),
+{
+    let state = RefCell::new((
+        Scheduler::<PatienceEvent>::new(),
+        std::collections::HashMap::<(usize, bool), u64>::new(),
+        0u64,
+    ));
+
+    let _ = run_loop(
+        tick,
+        |floors| {
+            let (scheduler, call_started, now) = &mut *state.borrow_mut();
+            for (floor_index, floor) in floors.iter().enumerate() {
+                for (is_up, lit) in [(true, floor.up), (false, floor.down)] {
+                    if lit && !call_started.contains_key(&(floor_index, is_up)) {
+                        call_started.insert((floor_index, is_up), *now);
+                        scheduler.schedule(
+                            *now + patience_ticks,
+                            PatienceEvent {
+                                floor_index,
+                                direction: if is_up { CallDirection::Up } else { CallDirection::Down },
+                            },
+                        );
+                    } else if !lit {
+                        call_started.remove(&(floor_index, is_up));
+                    }
+                }
+            }
+        },
+        |_, _, _| {
+            let (scheduler, call_started, now) = &mut *state.borrow_mut();
+            *now += 1;
+            for event in scheduler.advance_to(*now) {
+                let is_up = event.direction == CallDirection::Up;
+                if call_started.remove(&(event.floor_index, is_up)).is_some() {
+                    on_give_up(event.floor_index, is_up);
+                }
+            }
+            Ok(())
+        },
+    );
+}
+
+const REPLAY_MAGIC: &[u8; 4] = b"SAGR";
+const REPLAY_VERSION: u32 = 1;
+
+/// A command as captured into a replay file, decoded back from the same
+/// tagged wire format `write_commands` uses between controller and engine.
+#[derive(Clone, Copy, Debug)]
+pub enum RecordedCommand {
+    GoToFloor(i32),
+    Motor(f32),
+}
+
+fn write_elevator_frame(buf: &mut Vec<u8>, elevator: &Elevator) {
+    buf.extend_from_slice(&elevator.current_floor_val.to_le_bytes());
+    buf.extend_from_slice(&elevator.destination_floor_val.unwrap_or(-1).to_le_bytes());
+    buf.extend_from_slice(&elevator.percent_full_val.to_le_bytes());
+    buf.extend_from_slice(&elevator.location_val.to_le_bytes());
+    buf.extend_from_slice(&elevator.velocity_val.to_le_bytes());
+    buf.extend_from_slice(&elevator.acceleration_val.to_le_bytes());
+    buf.extend_from_slice(&(elevator.pressed_buttons.len() as u32).to_le_bytes());
+    for &button in &elevator.pressed_buttons {
+        buf.extend_from_slice(&button.to_le_bytes());
+    }
+}
+
+fn read_elevator_frame(reader: &mut impl Read, id: u32) -> io::Result<Elevator> {
+    let mut buf4 = [0u8; 4];
+
+    reader.read_exact(&mut buf4)?;
+    let current_floor = i32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let dest_raw = i32::from_le_bytes(buf4);
+    let destination_floor = if dest_raw == -1 { None } else { Some(dest_raw) };
+
+    reader.read_exact(&mut buf4)?;
+    let percent_full = f32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let location = f32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let velocity = f32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let acceleration = f32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let button_count = u32::from_le_bytes(buf4) as usize;
+
+    let mut pressed_buttons = Vec::with_capacity(button_count);
+    for _ in 0..button_count {
+        reader.read_exact(&mut buf4)?;
+        pressed_buttons.push(i32::from_le_bytes(buf4));
+    }
+
+    Ok(Elevator {
+        id,
+        current_floor_val: current_floor,
+        destination_floor_val: destination_floor,
+        percent_full_val: percent_full,
+        location_val: location,
+        velocity_val: velocity,
+        acceleration_val: acceleration,
+        pressed_buttons,
+        commands: Vec::new(),
+    })
+}
+
+fn write_floor_frame(buf: &mut Vec<u8>, floor: &Floor) {
+    buf.extend_from_slice(&floor.level_val.to_le_bytes());
+    buf.extend_from_slice(&floor.height_val.to_le_bytes());
+    buf.push(floor.up as u8);
+    buf.push(floor.down as u8);
+}
+
+fn read_floor_frame(reader: &mut impl Read) -> io::Result<Floor> {
+    let mut buf4 = [0u8; 4];
+    reader.read_exact(&mut buf4)?;
+    let level = i32::from_le_bytes(buf4);
+
+    reader.read_exact(&mut buf4)?;
+    let height = f32::from_le_bytes(buf4);
+
+    let mut buf1 = [0u8; 1];
+    reader.read_exact(&mut buf1)?;
+    let up = buf1[0] != 0;
+
+    reader.read_exact(&mut buf1)?;
+    let down = buf1[0] != 0;
+
+    Ok(Floor {
+        level_val: level,
+        height_val: height,
+        up,
+        down,
+    })
+}
+
+fn encode_frame(elevators: &[Elevator], floors: &[Floor], commands: &[(u32, Command)]) -> Vec<u8> {
+    let mut buf = Vec::new();
+
+    buf.extend_from_slice(&(elevators.len() as u32).to_le_bytes());
+    for elevator in elevators {
+        write_elevator_frame(&mut buf, elevator);
+    }
+
+    buf.extend_from_slice(&(floors.len() as u32).to_le_bytes());
+    for floor in floors {
+        write_floor_frame(&mut buf, floor);
+    }
+
+    buf.extend_from_slice(&(commands.len() as u32).to_le_bytes());
+    for (elevator_id, command) in commands {
+        buf.extend_from_slice(&elevator_id.to_le_bytes());
+        match command {
+            Command::GoToFloor(target_floor) => {
+                buf.push(0);
+                buf.extend_from_slice(&target_floor.to_le_bytes());
+            }
+            Command::Motor(acceleration) => {
+                buf.push(1);
+                buf.extend_from_slice(&acceleration.to_le_bytes());
+            }
+        }
+    }
+
+    buf
+}
+
+type Frame = (Vec<Elevator>, Vec<Floor>, Vec<(u32, RecordedCommand)>);
+
+fn decode_frame(payload: &[u8]) -> io::Result<Frame> {
+    let mut cursor = Cursor::new(payload);
+    let mut buf4 = [0u8; 4];
+
+    cursor.read_exact(&mut buf4)?;
+    let elevator_count = u32::from_le_bytes(buf4) as usize;
+    let mut elevators = Vec::with_capacity(elevator_count);
+    for id in 0..elevator_count {
+        elevators.push(read_elevator_frame(&mut cursor, id as u32)?);
+    }
+
+    cursor.read_exact(&mut buf4)?;
+    let floor_count = u32::from_le_bytes(buf4) as usize;
+    let mut floors = Vec::with_capacity(floor_count);
+    for _ in 0..floor_count {
+        floors.push(read_floor_frame(&mut cursor)?);
+    }
+
+    cursor.read_exact(&mut buf4)?;
+    let command_count = u32::from_le_bytes(buf4) as usize;
+    let mut commands = Vec::with_capacity(command_count);
+    for _ in 0..command_count {
+        cursor.read_exact(&mut buf4)?;
+        let elevator_id = u32::from_le_bytes(buf4);
+
+        let mut tag = [0u8; 1];
+        cursor.read_exact(&mut tag)?;
+
+        cursor.read_exact(&mut buf4)?;
+        let command = match tag[0] {
+            0 => RecordedCommand::GoToFloor(i32::from_le_bytes(buf4)),
+            1 => RecordedCommand::Motor(f32::from_le_bytes(buf4)),
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown recorded command tag {other}"),
+                ))
+            }
+        };
+
+        commands.push((elevator_id, command));
+    }
+
+    Ok((elevators, floors, commands))
+}
+
+/// Writes a replay file's magic/version header.
+fn write_replay_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(REPLAY_MAGIC)?;
+    writer.write_all(&REPLAY_VERSION.to_le_bytes())?;
+    writer.flush()
+}
+
+/// Writes one length-prefixed encoded frame, as produced by [`encode_frame`].
+fn write_replay_frame(writer: &mut impl Write, frame: &[u8]) -> io::Result<()> {
+    writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+    writer.write_all(frame)?;
+    writer.flush()
+}
+
+/// Wraps [`run`]'s stdin/stdout loop, additionally recording every tick's
+/// decoded state and emitted commands to `path` in a versioned binary
+/// format. Lets a live controller run be replayed, diffed, or re-fed into
+/// the [`Simulator`] offline.
+pub fn run_recording<F: FnMut(&mut [Elevator], &[Floor])>(
+    path: impl AsRef<Path>,
+    tick: F,
+) -> io::Result<()> {
+    let mut recording = BufWriter::new(File::create(path)?);
+    write_replay_header(&mut recording)?;
+
+    run_loop(tick, |_| {}, |elevators, floors, commands| {
+        let frame = encode_frame(elevators, floors, commands);
+        write_replay_frame(&mut recording, &frame)
+    })
+}
+
+/// One decoded tick from a replay file: its index, elevators, floors, and
+/// the commands emitted that tick.
+pub type ReplayFrame = (u32, Vec<Elevator>, Vec<Floor>, Vec<(u32, RecordedCommand)>);
+
+/// Reads back a file written by [`run_recording`], yielding each tick's
+/// index, decoded elevators/floors, and the commands emitted that tick.
+pub fn replay(path: impl AsRef<Path>) -> io::Result<Vec<ReplayFrame>> {
+    let mut file = BufReader::new(File::open(path)?);
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != REPLAY_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a saga replay file"));
+    }
+
+    let mut buf4 = [0u8; 4];
+    file.read_exact(&mut buf4)?;
+    let version = u32::from_le_bytes(buf4);
+    if version != REPLAY_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported replay version {version}"),
+        ));
+    }
+
+    let mut frames = Vec::new();
+    let mut tick_index = 0u32;
+    loop {
+        let mut len_buf = [0u8; 4];
+        match file.read_exact(&mut len_buf) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+
+        let len = u32::from_le_bytes(len_buf) as usize;
+        let mut payload = vec![0u8; len];
+        file.read_exact(&mut payload)?;
+
+        let (elevators, floors, commands) = decode_frame(&payload)?;
+        frames.push((tick_index, elevators, floors, commands));
+        tick_index += 1;
+    }
+
+    Ok(frames)
+}
+
+/// A seeded building configuration to evaluate a controller against.
+/// Identical seeds produce identical passenger streams, so scenarios are
+/// comparable across controllers and across runs.
+pub struct Scenario {
+    pub seed: u64,
+    pub num_floors: u32,
+    pub num_elevators: u32,
+    pub passenger_rate: f32,
+    pub duration_ticks: u32,
+}
+
+/// A controller's aggregated performance on one or more [`Scenario`]s.
+pub struct ControllerScore {
+    pub passengers_delivered: u32,
+    pub mean_wait_ticks: f32,
+    pub p95_wait_ticks: f32,
+    pub max_queue_len: u32,
+    pub rank: u32,
+}
+
+fn scenario_world_state(scenario: &Scenario) -> WorldState {
+    let mut height = 0.0;
+    let mut floors = Vec::with_capacity(scenario.num_floors as usize);
+    for level in 0..scenario.num_floors as i32 {
+        floors.push(Floor {
+            level_val: level,
+            height_val: height,
+            up: false,
+            down: false,
+        });
+        height += 4.0;
+    }
+
+    let elevators = (0..scenario.num_elevators)
+        .map(|id| Elevator {
+            id,
+            current_floor_val: 0,
+            destination_floor_val: None,
+            percent_full_val: 0.0,
+            location_val: 0.0,
+            velocity_val: 0.0,
+            acceleration_val: 0.0,
+            pressed_buttons: Vec::new(),
+            commands: Vec::new(),
+        })
+        .collect();
+
+    WorldState { elevators, floors }
+}
+
+/// Counts elevators that transitioned onto their destination floor this
+/// tick (edge-triggered on `previous` -> `next`), so a car idling at an
+/// already-serviced destination isn't counted as a fresh delivery on every
+/// subsequent tick.
+fn count_deliveries(previous: &WorldState, next: &WorldState) -> u32 {
+    next.elevators
+        .iter()
+        .filter(|elevator| {
+            let arrived_now = elevator.destination_floor_val == Some(elevator.current_floor_val);
+            let arrived_before = previous
+                .elevators
+                .iter()
+                .find(|e| e.id == elevator.id)
+                .is_some_and(|e| e.destination_floor_val == Some(e.current_floor_val));
+            arrived_now && !arrived_before
+        })
+        .count() as u32
+}
+
+/// A controller under test: the same `tick` closure shape `run` expects.
+pub type ControllerFn = Box<dyn FnMut(&mut [Elevator], &[Floor])>;
+
+/// Runs every controller against every scenario and aggregates the
+/// results, so strategies can be compared objectively on identical
+/// passenger streams instead of a single one-off `run`.
+pub fn run_tournament(controllers: &mut [ControllerFn], scenarios: &[Scenario]) -> Vec<ControllerScore> {
+    let mut scores: Vec<ControllerScore> = controllers
+        .iter_mut()
+        .map(|controller| {
+            let mut wait_ticks: Vec<f32> = Vec::new();
+            let mut passengers_delivered = 0;
+            let mut max_queue_len = 0;
+
+            for scenario in scenarios {
+                let mut rng = Rng::new(scenario.seed);
+                let motion = SmoothMotionController::new(1.0, 2.0);
+                let mut simulator = Simulator::new(motion, 1.0, scenario.passenger_rate, 600);
+                let mut state = scenario_world_state(scenario);
+
+                for _ in 0..scenario.duration_ticks {
+                    simulator.spawn(&mut state.floors, &mut rng);
+
+                    let queue_len = state
+                        .floors
+                        .iter()
+                        .filter(|f| f.up || f.down)
+                        .count() as u32;
+                    max_queue_len = max_queue_len.max(queue_len);
+
+                    controller(&mut state.elevators, &state.floors);
+
+                    let commands: Vec<(u32, i32)> = state
+                        .elevators
+                        .iter_mut()
+                        .flat_map(|e| e.commands.drain(..))
+                        .filter_map(|(id, command)| match command {
+                            Command::GoToFloor(floor) => Some((id, floor)),
+                            Command::Motor(_) => None,
+                        })
+                        .collect();
+
+                    let next_state = simulator.step(&state, &commands);
+                    passengers_delivered += count_deliveries(&state, &next_state);
+                    wait_ticks.extend(simulator.take_completed_waits().into_iter().map(|w| w as f32));
+
+                    state = next_state;
+                }
+            }
+
+            let mean_wait_ticks = if wait_ticks.is_empty() {
+                0.0
+            } else {
+                wait_ticks.iter().sum::<f32>() / wait_ticks.len() as f32
+            };
+            let mut sorted_waits = wait_ticks.clone();
+            sorted_waits.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let p95_wait_ticks = sorted_waits
+                .get(((sorted_waits.len() as f32 * 0.95) as usize).min(sorted_waits.len().saturating_sub(1)))
+                .copied()
+                .unwrap_or(0.0);
+
+            ControllerScore {
+                passengers_delivered,
+                mean_wait_ticks,
+                p95_wait_ticks,
+                max_queue_len,
+                rank: 0,
+            }
+        })
+        .collect();
+
+    let mut ranking: Vec<usize> = (0..scores.len()).collect();
+    ranking.sort_by(|&a, &b| {
+        scores[b]
+            .passengers_delivered
+            .cmp(&scores[a].passengers_delivered)
+    });
+    for (rank, index) in ranking.into_iter().enumerate() {
+        scores[index].rank = rank as u32 + 1;
+    }
+
+    scores
+}
+
+/// One (observation, action, reward, next_observation) transition
+/// collected while playing an episode through the [`Simulator`].
+pub struct Transition {
+    pub observation: Vec<f32>,
+    pub action: Vec<(u32, i32)>,
+    pub reward: f32,
+    pub next_observation: Vec<f32>,
+}
+
+/// A learnable controller: observes a flattened feature vector and
+/// produces elevator commands, and consumes transition batches to update
+/// itself. Lets [`Trainer`] stay agnostic to what kind of learner is
+/// behind it.
+pub trait Policy {
+    fn act(&mut self, obs: &[f32]) -> Vec<(u32, i32)>;
+    fn update(&mut self, batch: &[Transition]);
+}
+
+/// Summary of one episode played by [`Trainer::run_episode`].
+pub struct EpisodeStats {
+    pub total_reward: f32,
+    pub passengers_delivered: u32,
+    pub ticks: u32,
+}
+
+/// Double-buffered transition store: one buffer fills while the previous
+/// episode's buffer is drained for a policy update, flipped each episode
+/// so neither buffer needs reallocating mid-episode.
+struct ExperienceBuffers {
+    buffers: [Vec<Transition>; 2],
+    active: usize,
+}
+
+impl ExperienceBuffers {
+    fn new() -> Self {
+        Self {
+            buffers: [Vec::new(), Vec::new()],
+            active: 0,
+        }
+    }
+
+    fn push(&mut self, transition: Transition) {
+        self.buffers[self.active].push(transition);
+    }
+
+    /// Clears the buffer about to become active for the next episode and
+    /// hands back a reference to the one just finished, so a policy can
+    /// update from it without taking ownership — moving it out would force
+    /// every episode's fill to reallocate from scratch, exactly what
+    /// double-buffering is meant to avoid.
+    fn flip(&mut self) -> &[Transition] {
+        let finished = self.active;
+        self.active = 1 - self.active;
+        self.buffers[self.active].clear();
+        &self.buffers[finished]
+    }
+}
+
+/// Trains a [`Policy`] by playing episodes through the deterministic
+/// [`Simulator`] instead of hand-coding heuristics.
+pub struct Trainer {
+    pub simulator: Simulator,
+    pub ticks_per_episode: u32,
+    experience: ExperienceBuffers,
+}
+
+impl Trainer {
+    pub fn new(simulator: Simulator, ticks_per_episode: u32) -> Self {
+        Self {
+            simulator,
+            ticks_per_episode,
+            experience: ExperienceBuffers::new(),
+        }
+    }
+
+    /// Plays one episode of `scenario` through `policy`, collecting
+    /// transitions into the active experience buffer, then flips it and
+    /// runs `policy.update` on the previous episode's buffer.
+    pub fn run_episode(
+        &mut self,
+        policy: &mut impl Policy,
+        scenario: &Scenario,
+        rng: &mut Rng,
+    ) -> EpisodeStats {
+        let mut state = scenario_world_state(scenario);
+        let mut total_reward = 0.0;
+        let mut passengers_delivered = 0;
+
+        for _ in 0..self.ticks_per_episode {
+            self.simulator.spawn(&mut state.floors, rng);
+
+            let observation = observe(&state);
+            let action = policy.act(&observation);
+            let next_state = self.simulator.step(&state, &action);
+
+            let waiting_floors = next_state.floors.iter().filter(|f| f.up || f.down).count() as f32;
+            let delivered = count_deliveries(&state, &next_state);
+            passengers_delivered += delivered;
+            let reward = delivered as f32 - waiting_floors;
+            total_reward += reward;
+
+            self.experience.push(Transition {
+                observation,
+                action,
+                reward,
+                next_observation: observe(&next_state),
+            });
+
+            state = next_state;
+        }
+
+        let batch = self.experience.flip();
+        policy.update(batch);
+
+        EpisodeStats {
+            total_reward,
+            passengers_delivered,
+            ticks: self.ticks_per_episode,
+        }
+    }
+}
+
+/// Flattens the world into the per-elevator/per-floor feature vector a
+/// [`Policy`] consumes: for each elevator, current floor, destination,
+/// load, and pressed-button count; for each floor, up/down call state.
+fn observe(state: &WorldState) -> Vec<f32> {
+    let mut features = Vec::with_capacity(state.elevators.len() * 4 + state.floors.len() * 2);
+
+    for elevator in &state.elevators {
+        features.push(elevator.current_floor_val as f32);
+        features.push(elevator.destination_floor_val.map_or(-1.0, |d| d as f32));
+        features.push(elevator.percent_full_val);
+        features.push(elevator.pressed_buttons.len() as f32);
+    }
+
+    for floor in &state.floors {
+        features.push(floor.up as u8 as f32);
+        features.push(floor.down as u8 as f32);
+    }
+
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn four_floor_state() -> WorldState {
+        let floors = (0..4)
+            .map(|level| Floor {
+                level_val: level,
+                height_val: level as f32 * 4.0,
+                up: false,
+                down: false,
+            })
+            .collect();
+        let elevators = vec![Elevator {
+            id: 0,
+            current_floor_val: 0,
+            destination_floor_val: None,
+            percent_full_val: 0.0,
+            location_val: 0.0,
+            velocity_val: 0.0,
+            acceleration_val: 0.0,
+            pressed_buttons: Vec::new(),
+            commands: Vec::new(),
+        }];
+        WorldState { elevators, floors }
+    }
+
+    #[test]
+    fn step_syncs_current_floor_from_location() {
+        let motion = SmoothMotionController::new(1.0, 2.0);
+        let mut simulator = Simulator::new(motion, 1.0, 0.0, 600);
+        let mut state = four_floor_state();
+
+        for _ in 0..200 {
+            state = simulator.step(&state, &[(0, 3)]);
+        }
+
+        assert_eq!(state.elevators[0].current_floor_val, 3);
+        assert!((state.elevators[0].location_val - 12.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn motion_converges_when_approaching_target_mid_flight() {
+        let motion = SmoothMotionController::new(1.0, 2.0);
+        let mut state = four_floor_state();
+        state.elevators[0].location_val = 10.0;
+        state.elevators[0].velocity_val = 2.0;
+        state.elevators[0].destination_floor_val = Some(3);
+
+        let floor_heights: Vec<f32> = state.floors.iter().map(|f| f.height_val).collect();
+        for _ in 0..40 {
+            motion.tick(&mut state.elevators, &floor_heights, 1.0);
+        }
+
+        assert!((state.elevators[0].location_val - 12.0).abs() < 0.01);
+        assert_eq!(state.elevators[0].velocity_val, 0.0);
+    }
+
+    fn sample_frame_fixtures() -> (Vec<Elevator>, Vec<Floor>, Vec<(u32, Command)>) {
+        let mut elevator = four_floor_state().elevators.remove(0);
+        elevator.destination_floor_val = Some(2);
+        elevator.location_val = 4.5;
+        elevator.velocity_val = 1.0;
+        elevator.acceleration_val = -0.5;
+        elevator.pressed_buttons = vec![1, 3];
+
+        let floors = four_floor_state().floors;
+        let commands = vec![(0u32, Command::GoToFloor(2)), (1u32, Command::Motor(0.75))];
+
+        (vec![elevator], floors, commands)
+    }
+
+    #[test]
+    fn encode_frame_round_trips_through_decode_frame() {
+        let (elevators, floors, commands) = sample_frame_fixtures();
+
+        let payload = encode_frame(&elevators, &floors, &commands);
+        let (decoded_elevators, decoded_floors, decoded_commands) = decode_frame(&payload).unwrap();
+
+        assert_eq!(decoded_elevators.len(), 1);
+        assert_eq!(decoded_elevators[0].id, 0);
+        assert_eq!(decoded_elevators[0].current_floor_val, elevators[0].current_floor_val);
+        assert_eq!(decoded_elevators[0].destination_floor_val, Some(2));
+        assert_eq!(decoded_elevators[0].location_val, 4.5);
+        assert_eq!(decoded_elevators[0].velocity_val, 1.0);
+        assert_eq!(decoded_elevators[0].acceleration_val, -0.5);
+        assert_eq!(decoded_elevators[0].pressed_buttons, vec![1, 3]);
+
+        assert_eq!(decoded_floors.len(), floors.len());
+        assert_eq!(decoded_floors[3].level_val, floors[3].level_val);
+
+        assert_eq!(decoded_commands.len(), 2);
+        match decoded_commands[0].1 {
+            RecordedCommand::GoToFloor(floor) => assert_eq!(floor, 2),
+            RecordedCommand::Motor(_) => panic!("expected GoToFloor"),
+        }
+        match decoded_commands[1].1 {
+            RecordedCommand::Motor(acceleration) => assert_eq!(acceleration, 0.75),
+            RecordedCommand::GoToFloor(_) => panic!("expected Motor"),
+        }
+    }
+
+    #[test]
+    fn replay_reads_back_a_file_written_in_run_recordings_format() {
+        let path = std::env::temp_dir().join("saga_replay_round_trip_test.bin");
+        let (elevators, floors, commands) = sample_frame_fixtures();
+
+        {
+            let mut file = BufWriter::new(File::create(&path).unwrap());
+            write_replay_header(&mut file).unwrap();
+            write_replay_frame(&mut file, &encode_frame(&elevators, &floors, &commands)).unwrap();
+            write_replay_frame(&mut file, &encode_frame(&elevators, &floors, &[])).unwrap();
+        }
+
+        let frames = replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].0, 0);
+        assert_eq!(frames[1].0, 1);
+        assert_eq!(frames[0].1[0].pressed_buttons, vec![1, 3]);
+        assert_eq!(frames[0].3.len(), 2);
+        assert!(frames[1].3.is_empty());
+    }
+
+    #[test]
+    fn delivery_is_counted_once_not_every_idle_tick() {
+        let motion = SmoothMotionController::new(1.0, 2.0);
+        let mut simulator = Simulator::new(motion, 1.0, 0.0, 600);
+        let mut state = four_floor_state();
+        let mut deliveries = 0;
+
+        for _ in 0..210 {
+            let next_state = simulator.step(&state, &[(0, 3)]);
+            deliveries += count_deliveries(&state, &next_state);
+            state = next_state;
+        }
+
+        assert_eq!(deliveries, 1);
+    }
+
+    #[test]
+    fn completed_wait_is_measured_in_ticks_not_fixed_at_one() {
+        let motion = SmoothMotionController::new(1.0, 2.0);
+        let mut simulator = Simulator::new(motion, 1.0, 1.0, 600);
+        let mut state = four_floor_state();
+        let mut rng = Rng::new(42);
+
+        simulator.spawn(&mut state.floors, &mut rng);
+        assert!(state.floors[3].up);
+
+        for _ in 0..5 {
+            state = simulator.step(&state, &[]);
+        }
+        state = simulator.step(&state, &[(0, 3)]);
+        let _ = state;
+
+        let mut waits = simulator.take_completed_waits();
+        waits.sort_unstable();
+        assert_eq!(waits, vec![5, 5]);
+    }
+
+    #[test]
+    fn run_tournament_ranks_a_better_controller_above_a_worse_one() {
+        let scenarios = vec![Scenario {
+            seed: 1,
+            num_floors: 4,
+            num_elevators: 1,
+            passenger_rate: 1.0,
+            duration_ticks: 250,
+        }];
+
+        let mut controllers: Vec<ControllerFn> = vec![
+            Box::new(|elevators: &mut [Elevator], _floors: &[Floor]| {
+                for elevator in elevators {
+                    if elevator.destination_floor().is_none() {
+                        let next = if elevator.current_floor() == 0 { 3 } else { 0 };
+                        elevator.go_to_floor(next);
+                    }
+                }
+            }),
+            Box::new(|_elevators: &mut [Elevator], _floors: &[Floor]| {
+                // Never dispatches anywhere, so nothing is ever delivered.
+            }),
+        ];
+
+        let scores = run_tournament(&mut controllers, &scenarios);
+
+        assert_eq!(scores[1].passengers_delivered, 0);
+        assert!(scores[0].passengers_delivered > scores[1].passengers_delivered);
+        assert_eq!(scores[0].rank, 1);
+        assert_eq!(scores[1].rank, 2);
+    }
+
+    #[test]
+    fn scheduler_fires_event_once_deadline_is_reached() {
+        let mut scheduler: Scheduler<u32> = Scheduler::new();
+        scheduler.schedule(3, 42);
+
+        assert!(scheduler.advance_to(2).is_empty());
+        assert_eq!(scheduler.advance_to(3), vec![42]);
+        assert!(scheduler.advance_to(10).is_empty());
+    }
+
+    #[test]
+    fn scheduler_jumps_straight_to_a_far_off_deadline() {
+        let mut scheduler: Scheduler<&'static str> = Scheduler::new();
+        // Past a single level-0 window (64 ticks), so this cascades down
+        // from a higher level before it can fire.
+        scheduler.schedule(500, "late");
+        scheduler.schedule(5, "early");
+
+        let expired = scheduler.advance_to(500);
+        assert_eq!(expired, vec!["early", "late"]);
+    }
+
+    #[test]
+    fn scheduler_fires_on_time_at_a_cascade_boundary() {
+        let mut scheduler: Scheduler<&'static str> = Scheduler::new();
+        // 128 is an exact multiple of the level-0 span (64), so it cascades
+        // down from level 1 on the very tick it's due, instead of being
+        // re-homed into an already-drained level-0 slot.
+        scheduler.schedule(128, "on-boundary");
+
+        assert_eq!(scheduler.advance_to(128), vec!["on-boundary"]);
+    }
+
+    #[test]
+    fn experience_buffer_flip_reuses_capacity_instead_of_reallocating() {
+        let transition = || Transition {
+            observation: Vec::new(),
+            action: Vec::new(),
+            reward: 0.0,
+            next_observation: Vec::new(),
+        };
+        let mut experience = ExperienceBuffers::new();
+
+        for _ in 0..8 {
+            experience.push(transition());
+        }
+        assert_eq!(experience.flip().len(), 8);
+        for _ in 0..8 {
+            experience.push(transition());
+        }
+        assert_eq!(experience.flip().len(), 8);
+
+        // Buffer 0 filled, was flipped out, then flipped back in as the
+        // active buffer two episodes later: it should have been cleared in
+        // place, not reallocated from scratch.
+        assert!(experience.buffers[0].capacity() >= 8);
     }
 }